@@ -1,6 +1,9 @@
 use crate::error::Error;
 use std::sync::Mutex;
-use unicorn_core::{Engine as CoreEngine, EngineAction as CoreEngineAction};
+use unicorn_core::{
+    Completion as CoreCompletion, Engine as CoreEngine, EngineAction as CoreEngineAction,
+    EngineConfig as CoreEngineConfig, FuzzyMatch as CoreFuzzyMatch,
+};
 
 #[derive(uniffi::Enum)]
 pub enum EngineAction {
@@ -10,6 +13,72 @@ pub enum EngineAction {
     ShowCandidates { text: String },
 }
 
+#[derive(uniffi::Record)]
+pub struct Completion {
+    pub suffix: String,
+    pub text: String,
+}
+
+impl From<CoreCompletion> for Completion {
+    fn from(completion: CoreCompletion) -> Self {
+        Self {
+            suffix: completion.suffix,
+            text: completion.text,
+        }
+    }
+}
+
+#[derive(uniffi::Record)]
+pub struct FuzzyMatch {
+    pub text: String,
+    pub distance: u32,
+}
+
+impl From<CoreFuzzyMatch> for FuzzyMatch {
+    fn from(m: CoreFuzzyMatch) -> Self {
+        Self {
+            text: m.text,
+            distance: m.distance,
+        }
+    }
+}
+
+/// Mirrors [`unicorn_core::EngineConfig`] for the FFI boundary. `uniffi` has no native `char`
+/// type, so `trigger`/`alternate_triggers` are single-character strings and are validated
+/// in [`EngineConfig::try_into_core`].
+#[derive(uniffi::Record)]
+pub struct EngineConfig {
+    pub trigger: String,
+    pub alternate_triggers: Vec<String>,
+    pub table_sources: Vec<String>,
+}
+
+impl EngineConfig {
+    fn single_char(label: &str, s: &str) -> Result<char, Error> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(Error::Init {
+                message: format!("{label} must be exactly one character, got {s:?}"),
+            }),
+        }
+    }
+
+    fn try_into_core(self) -> Result<CoreEngineConfig, Error> {
+        let trigger = Self::single_char("trigger", &self.trigger)?;
+        let alternate_triggers = self
+            .alternate_triggers
+            .iter()
+            .map(|s| Self::single_char("alternate_triggers entry", s))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(CoreEngineConfig {
+            trigger,
+            alternate_triggers,
+            table_sources: self.table_sources,
+        })
+    }
+}
+
 #[derive(uniffi::Object)]
 pub struct Engine {
     inner: Mutex<CoreEngine>,
@@ -37,6 +106,49 @@ impl Engine {
         Self::new(json_data)
     }
 
+    /// Loads a table previously produced by [`compile`] from its `mmap`ed binary form,
+    /// avoiding the JSON deserialization `new`/`new_from_path` pay on every startup.
+    #[uniffi::constructor]
+    pub fn new_from_compiled(path: String) -> Result<Self, Error> {
+        match CoreEngine::new_from_compiled(path) {
+            Ok(engine) => Ok(Self {
+                inner: Mutex::new(engine),
+            }),
+            Err(e) => Err(Error::Init {
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    /// Like [`Self::new`], but also loads (and later saves) a per-user selection-frequency
+    /// sidecar so `get_candidates` can rank by what this user actually picks.
+    #[uniffi::constructor]
+    pub fn new_with_history(json_data: String, history_path: String) -> Result<Self, Error> {
+        match CoreEngine::new_with_history(&json_data, history_path) {
+            Ok(engine) => Ok(Self {
+                inner: Mutex::new(engine),
+            }),
+            Err(e) => Err(Error::Init {
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    /// Builds an engine that deep-merges every table in `config.table_sources` and activates
+    /// on `config.trigger` (plus any `config.alternate_triggers`) instead of the hardcoded `\`.
+    #[uniffi::constructor]
+    pub fn new_with_config(config: EngineConfig) -> Result<Self, Error> {
+        let core_config = config.try_into_core()?;
+        match CoreEngine::new_with_config(&core_config) {
+            Ok(engine) => Ok(Self {
+                inner: Mutex::new(engine),
+            }),
+            Err(e) => Err(Error::Init {
+                message: e.to_string(),
+            }),
+        }
+    }
+
     pub fn process_key(&self, char_code: u32) -> Vec<EngineAction> {
         let mut engine = self.inner.lock().unwrap();
         if let Some(c) = std::char::from_u32(char_code) {
@@ -62,6 +174,24 @@ impl Engine {
         engine.get_candidates()
     }
 
+    pub fn get_completions(&self, max_results: u32) -> Vec<Completion> {
+        let engine = self.inner.lock().unwrap();
+        engine
+            .get_completions(max_results as usize)
+            .into_iter()
+            .map(Completion::from)
+            .collect()
+    }
+
+    pub fn fuzzy_candidates(&self, max_distance: u32) -> Vec<FuzzyMatch> {
+        let engine = self.inner.lock().unwrap();
+        engine
+            .fuzzy_candidates(max_distance)
+            .into_iter()
+            .map(FuzzyMatch::from)
+            .collect()
+    }
+
     pub fn select_candidate(&self, index: u32) {
         let mut engine = self.inner.lock().unwrap();
         engine.select_candidate(index as usize);
@@ -71,4 +201,28 @@ impl Engine {
         let mut engine = self.inner.lock().unwrap();
         engine.deactivate();
     }
+
+    pub fn lookup_sequences(&self, symbol: String) -> Vec<String> {
+        let engine = self.inner.lock().unwrap();
+        engine.lookup_sequences(&symbol)
+    }
+
+    pub fn save_history(&self) -> Result<(), Error> {
+        let engine = self.inner.lock().unwrap();
+        engine.save_history().map_err(|e| Error::Init {
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Flattens a JSON table into the binary arena format read by [`Engine::new_from_compiled`],
+/// writing it to `output_path` so a frontend can compile a table once at build time.
+#[uniffi::export]
+pub fn compile_trie(json_data: String, output_path: String) -> Result<(), Error> {
+    let bytes = unicorn_core::compile(&json_data).map_err(|e| Error::Init {
+        message: e.to_string(),
+    })?;
+    std::fs::write(output_path, bytes).map_err(|e| Error::Init {
+        message: e.to_string(),
+    })
 }
@@ -0,0 +1,395 @@
+//! A flattened, mmap-friendly binary encoding of a [`TrieNode`] tree.
+//!
+//! [`compile`] walks a JSON-sourced trie once and lays it out as a contiguous arena of
+//! fixed-size node records, an edge pool (sorted `(char, child_index)` pairs per node for
+//! binary search), a candidate-entry pool (offset/length into a shared string blob) and the
+//! string blob itself. [`CompiledTrie`] then reads that arena directly out of an `mmap`ed
+//! file by integer offset, so large tables (tens of thousands of entries) load without
+//! `serde_json` deserialization and traverse without `HashMap` lookups or node cloning.
+
+use crate::engine::TrieNode;
+use memmap2::Mmap;
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"UNIC";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 4 + 4 * 6;
+const NODE_RECORD_LEN: usize = 4 * 4;
+const EDGE_RECORD_LEN: usize = 4 * 2;
+const CANDIDATE_RECORD_LEN: usize = 4 * 2;
+
+/// Flattens `json` (in the same `TrieNode` shape accepted by [`crate::Engine::new`]) into the
+/// binary arena format read by [`CompiledTrie`].
+pub fn compile(json: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let root: TrieNode = serde_json::from_str(json)?;
+
+    let mut builder = Builder::default();
+    let root_index = builder.add_node(&root);
+
+    let mut out = Vec::with_capacity(
+        HEADER_LEN
+            + builder.nodes.len() * NODE_RECORD_LEN
+            + builder.edges.len() * EDGE_RECORD_LEN
+            + builder.candidates.len() * CANDIDATE_RECORD_LEN
+            + builder.strings.len(),
+    );
+
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&(builder.nodes.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(builder.edges.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(builder.candidates.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(builder.strings.len() as u32).to_le_bytes());
+    out.extend_from_slice(&root_index.to_le_bytes());
+
+    for node in &builder.nodes {
+        out.extend_from_slice(&node.edges_offset.to_le_bytes());
+        out.extend_from_slice(&node.edges_len.to_le_bytes());
+        out.extend_from_slice(&node.candidates_offset.to_le_bytes());
+        out.extend_from_slice(&node.candidates_len.to_le_bytes());
+    }
+    for (ch, child_index) in &builder.edges {
+        out.extend_from_slice(&ch.to_le_bytes());
+        out.extend_from_slice(&child_index.to_le_bytes());
+    }
+    for (str_offset, str_len) in &builder.candidates {
+        out.extend_from_slice(&str_offset.to_le_bytes());
+        out.extend_from_slice(&str_len.to_le_bytes());
+    }
+    out.extend_from_slice(&builder.strings);
+
+    Ok(out)
+}
+
+#[derive(Default)]
+struct NodeRecord {
+    edges_offset: u32,
+    edges_len: u32,
+    candidates_offset: u32,
+    candidates_len: u32,
+}
+
+#[derive(Default)]
+struct Builder {
+    nodes: Vec<NodeRecord>,
+    edges: Vec<(u32, u32)>,
+    candidates: Vec<(u32, u32)>,
+    strings: Vec<u8>,
+}
+
+impl Builder {
+    /// Adds `node` (and its whole subtree) to the arena, returning its node index.
+    ///
+    /// A node's own edges are reserved as a contiguous run *before* recursing into any
+    /// child, so the `(edges_offset, edges_len)` range stays valid even though recursing
+    /// appends further edges (belonging to descendants) to the same pool. Child indices are
+    /// only known once the child itself has been added, so they're patched in afterwards.
+    fn add_node(&mut self, node: &TrieNode) -> u32 {
+        let index = self.nodes.len() as u32;
+        self.nodes.push(NodeRecord::default());
+
+        let candidates_offset = self.candidates.len() as u32;
+        if let Some(candidates) = &node.candidates {
+            for candidate in candidates {
+                let str_offset = self.strings.len() as u32;
+                self.strings.extend_from_slice(candidate.as_bytes());
+                self.candidates
+                    .push((str_offset, candidate.len() as u32));
+            }
+        }
+        let candidates_len = self.candidates.len() as u32 - candidates_offset;
+
+        let mut children: Vec<(char, &TrieNode)> = node
+            .children
+            .iter()
+            .filter_map(|(key, child)| key.chars().next().map(|ch| (ch, child)))
+            .collect();
+        children.sort_by_key(|(ch, _)| *ch);
+
+        let edges_offset = self.edges.len() as u32;
+        for (ch, _) in &children {
+            self.edges.push((*ch as u32, 0));
+        }
+        let edges_len = children.len() as u32;
+
+        self.nodes[index as usize] = NodeRecord {
+            edges_offset,
+            edges_len,
+            candidates_offset,
+            candidates_len,
+        };
+
+        for (i, (_, child)) in children.iter().enumerate() {
+            let child_index = self.add_node(child);
+            self.edges[edges_offset as usize + i].1 = child_index;
+        }
+
+        index
+    }
+}
+
+/// A [`compile`]d trie, read directly out of an `mmap`ed byte slice.
+///
+/// Traversal works by integer node index instead of `HashMap` lookups, and never clones a
+/// subtree: candidates are only materialized into `String`s on demand.
+pub struct CompiledTrie {
+    mmap: Mmap,
+    node_count: u32,
+    edges_offset: usize,
+    candidates_offset: usize,
+    strings_offset: usize,
+    root_index: u32,
+}
+
+impl CompiledTrie {
+    /// Memory-maps `path` and validates its header, then bounds-checks every node, edge and
+    /// candidate record. The string blob is only paged in as candidates are read.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::from_mmap(mmap)
+    }
+
+    fn from_mmap(mmap: Mmap) -> Result<Self, Box<dyn Error>> {
+        if mmap.len() < HEADER_LEN || &mmap[0..4] != MAGIC {
+            return Err("not a compiled unicorn trie".into());
+        }
+        let version = read_u32(&mmap, 4);
+        if version != VERSION {
+            return Err(format!("unsupported compiled trie version: {version}").into());
+        }
+
+        let node_count = read_u32(&mmap, 8);
+        let edge_count = read_u32(&mmap, 12);
+        let candidate_count = read_u32(&mmap, 16);
+        let string_bytes_len = read_u32(&mmap, 20);
+        let root_index = read_u32(&mmap, 24);
+
+        let nodes_offset = HEADER_LEN;
+        let edges_offset = nodes_offset + node_count as usize * NODE_RECORD_LEN;
+        let candidates_offset = edges_offset + edge_count as usize * EDGE_RECORD_LEN;
+        let strings_offset = candidates_offset + candidate_count as usize * CANDIDATE_RECORD_LEN;
+        let expected_len = strings_offset + string_bytes_len as usize;
+        if mmap.len() != expected_len {
+            return Err("compiled trie length does not match its header".into());
+        }
+        if root_index >= node_count {
+            return Err("compiled trie root index out of bounds".into());
+        }
+
+        for i in 0..node_count as usize {
+            let offset = nodes_offset + i * NODE_RECORD_LEN;
+            let edges_end = read_u32(&mmap, offset) as u64 + read_u32(&mmap, offset + 4) as u64;
+            let candidates_end =
+                read_u32(&mmap, offset + 8) as u64 + read_u32(&mmap, offset + 12) as u64;
+            if edges_end > edge_count as u64 || candidates_end > candidate_count as u64 {
+                return Err("compiled trie node references an out-of-bounds edge/candidate range".into());
+            }
+        }
+        for i in 0..edge_count as usize {
+            let offset = edges_offset + i * EDGE_RECORD_LEN;
+            if read_u32(&mmap, offset + 4) >= node_count {
+                return Err("compiled trie edge references an out-of-bounds child node".into());
+            }
+        }
+        for i in 0..candidate_count as usize {
+            let offset = candidates_offset + i * CANDIDATE_RECORD_LEN;
+            let str_end = read_u32(&mmap, offset) as u64 + read_u32(&mmap, offset + 4) as u64;
+            if str_end > string_bytes_len as u64 {
+                return Err("compiled trie candidate references an out-of-bounds string range".into());
+            }
+        }
+
+        Ok(Self {
+            mmap,
+            node_count,
+            edges_offset,
+            candidates_offset,
+            strings_offset,
+            root_index,
+        })
+    }
+
+    pub fn root_index(&self) -> u32 {
+        self.root_index
+    }
+
+    fn node_record(&self, index: u32) -> NodeRecord {
+        debug_assert!(index < self.node_count);
+        let offset = HEADER_LEN + index as usize * NODE_RECORD_LEN;
+        NodeRecord {
+            edges_offset: read_u32(&self.mmap, offset),
+            edges_len: read_u32(&self.mmap, offset + 4),
+            candidates_offset: read_u32(&self.mmap, offset + 8),
+            candidates_len: read_u32(&self.mmap, offset + 12),
+        }
+    }
+
+    /// Every `(char, child_index)` edge out of `index`, sorted by `char`.
+    pub fn edges(&self, index: u32) -> Vec<(char, u32)> {
+        let record = self.node_record(index);
+        (0..record.edges_len)
+            .map(|i| {
+                let offset =
+                    self.edges_offset + (record.edges_offset + i) as usize * EDGE_RECORD_LEN;
+                let ch = char::from_u32(read_u32(&self.mmap, offset)).unwrap_or('\u{FFFD}');
+                let child_index = read_u32(&self.mmap, offset + 4);
+                (ch, child_index)
+            })
+            .collect()
+    }
+
+    pub fn has_children(&self, index: u32) -> bool {
+        self.node_record(index).edges_len > 0
+    }
+
+    /// Binary-searches `index`'s edges for `c`, returning the child node index if present.
+    pub fn find_child(&self, index: u32, c: char) -> Option<u32> {
+        let record = self.node_record(index);
+        let target = c as u32;
+        let mut lo = 0u32;
+        let mut hi = record.edges_len;
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let offset =
+                self.edges_offset + (record.edges_offset + mid) as usize * EDGE_RECORD_LEN;
+            let mid_ch = read_u32(&self.mmap, offset);
+            match mid_ch.cmp(&target) {
+                std::cmp::Ordering::Equal => return Some(read_u32(&self.mmap, offset + 4)),
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+            }
+        }
+        None
+    }
+
+    /// The candidate strings attached to `index`, materialized fresh from the string blob.
+    pub fn candidates(&self, index: u32) -> Vec<String> {
+        let record = self.node_record(index);
+        (0..record.candidates_len)
+            .map(|i| {
+                let offset = self.candidates_offset
+                    + (record.candidates_offset + i) as usize * CANDIDATE_RECORD_LEN;
+                let str_offset = read_u32(&self.mmap, offset) as usize;
+                let str_len = read_u32(&self.mmap, offset + 4) as usize;
+                let start = self.strings_offset + str_offset;
+                String::from_utf8_lossy(&self.mmap[start..start + str_len]).into_owned()
+            })
+            .collect()
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Reconstructs a [`TrieNode`] tree from a compiled arena, mainly useful for tests that want
+/// to assert a round-trip through [`compile`].
+#[cfg(test)]
+pub(crate) fn to_trie_node(trie: &CompiledTrie, index: u32) -> TrieNode {
+    use std::collections::HashMap;
+
+    let candidates = trie.candidates(index);
+    let children = trie
+        .edges(index)
+        .into_iter()
+        .map(|(ch, child_index)| (ch.to_string(), to_trie_node(trie, child_index)))
+        .collect::<HashMap<_, _>>();
+    TrieNode {
+        candidates: if candidates.is_empty() {
+            None
+        } else {
+            Some(candidates)
+        },
+        children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const TEST_JSON: &str = r#"{
+        "l": {
+            ">>": ["λ", "←"],
+            "a": {
+                "m": {
+                    ">>": ["λ"]
+                }
+            }
+        },
+        "a": {
+            "l": {
+                "p": {
+                    "h": {
+                        "a": {
+                            ">>": ["α"]
+                        }
+                    }
+                }
+            }
+        }
+    }"#;
+
+    fn compiled_fixture(json: &str) -> (NamedTempFile, CompiledTrie) {
+        let bytes = compile(json).unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+        let trie = CompiledTrie::open(file.path()).unwrap();
+        (file, trie)
+    }
+
+    #[test]
+    fn test_compile_and_find_child() {
+        let (_file, trie) = compiled_fixture(TEST_JSON);
+
+        let l = trie.find_child(trie.root_index(), 'l').unwrap();
+        assert_eq!(trie.candidates(l), vec!["λ", "←"]);
+
+        let a = trie.find_child(trie.root_index(), 'a').unwrap();
+        assert!(trie.candidates(a).is_empty());
+        assert!(trie.has_children(a));
+
+        assert!(trie.find_child(trie.root_index(), 'z').is_none());
+    }
+
+    #[test]
+    fn test_compile_round_trip_deep_path() {
+        let (_file, trie) = compiled_fixture(TEST_JSON);
+
+        let mut index = trie.root_index();
+        for ch in ['a', 'l', 'p', 'h', 'a'] {
+            index = trie.find_child(index, ch).unwrap();
+        }
+        assert_eq!(trie.candidates(index), vec!["α"]);
+    }
+
+    #[test]
+    fn test_open_rejects_node_with_out_of_bounds_edge_range() {
+        let mut bytes = compile(TEST_JSON).unwrap();
+        // Corrupt the root node's edges_len field so its edge range runs past the edge pool.
+        let offset = HEADER_LEN + 4;
+        bytes[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+        assert!(CompiledTrie::open(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_to_trie_node_round_trip() {
+        let original: TrieNode = serde_json::from_str(TEST_JSON).unwrap();
+        let (_file, trie) = compiled_fixture(TEST_JSON);
+        let rebuilt = to_trie_node(&trie, trie.root_index());
+
+        assert_eq!(rebuilt.children.len(), original.children.len());
+        assert_eq!(
+            to_trie_node(&trie, trie.find_child(trie.root_index(), 'l').unwrap()).candidates,
+            original.children["l"].candidates
+        );
+    }
+}
@@ -1,7 +1,14 @@
+use crate::compiled::CompiledTrie;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// Selection counts keyed by `buffer`, then by the candidate string chosen for it. Used to
+/// reorder [`Engine::get_candidates`] towards whatever a given user picks most often.
+type FrequencyHistory = HashMap<String, HashMap<String, u64>>;
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct TrieNode {
     #[serde(rename = ">>")]
@@ -10,6 +17,23 @@ pub struct TrieNode {
     pub children: HashMap<String, TrieNode>,
 }
 
+/// Configures an [`Engine`] built with [`Engine::new_with_config`]: which key(s) activate
+/// composition, and which JSON tables to deep-merge into a single trie.
+///
+/// Deserializable the same way a `Cargo.toml`-style manifest would be, so a frontend can ship
+/// this as a small JSON/TOML config file alongside its tables.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EngineConfig {
+    /// The key that activates composition, e.g. `'\\'`.
+    pub trigger: char,
+    /// Additional keys that activate composition just like `trigger`.
+    #[serde(default)]
+    pub alternate_triggers: Vec<char>,
+    /// Paths to JSON trie tables, deep-merged in order (children unioned recursively, `>>`
+    /// lists concatenated with de-duplication).
+    pub table_sources: Vec<String>,
+}
+
 #[derive(Debug, PartialEq)]
 /// Represents the action the frontend should take in response to a key event.
 pub enum EngineAction {
@@ -32,34 +56,336 @@ pub enum EngineAction {
     ShowCandidates(String),
 }
 
+/// A candidate reachable from the current composition by typing `suffix` more keys.
+///
+/// Returned by [`Engine::get_completions`], which walks the whole subtree under the
+/// current node rather than requiring an exact terminal match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    /// The remaining keys (after the current buffer) that produce `text`.
+    pub suffix: String,
+    /// The candidate string itself.
+    pub text: String,
+}
+
+/// A candidate found by [`Engine::fuzzy_candidates`], together with its Levenshtein distance
+/// from the typed buffer (ignoring the leading trigger character).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    /// The candidate string itself.
+    pub text: String,
+    /// Edit distance between the query and the key path that produced `text`.
+    pub distance: u32,
+}
+
+/// The trie backing an [`Engine`]: either the `serde_json`-deserialized tree used for small,
+/// everyday tables, or a [`CompiledTrie`] mmap for large ones (see [`crate::compiled`]).
+enum Backing {
+    Tree(Arc<TrieNode>),
+    Compiled(Arc<CompiledTrie>),
+}
+
+/// A node of the current composition path. Cheap to carry around either way: an `Arc` clone
+/// for the tree backing, or a bare integer index for the compiled one.
+#[derive(Clone)]
+enum PathEntry {
+    Tree(Arc<TrieNode>),
+    Compiled(u32),
+}
+
 pub struct Engine {
-    root: Arc<TrieNode>,
-    path: Vec<Arc<TrieNode>>,
+    backing: Backing,
+    path: Vec<PathEntry>,
     buffer: String,
     active: bool,
     selected_candidate: usize,
+    history: FrequencyHistory,
+    history_path: Option<PathBuf>,
+    /// Maps a candidate string to every trigger-prefixed key sequence that produces it. Walking
+    /// the whole trie is deferred until the first [`Self::lookup_sequences`] call (and cached
+    /// after that) so constructing an `Engine` over a large [`CompiledTrie`] does no trie walk.
+    reverse_index: OnceCell<HashMap<String, Vec<String>>>,
+    /// The key that activates composition. `'\\'` unless built via [`Self::new_with_config`].
+    trigger: char,
+    /// Additional keys that activate composition just like `trigger`.
+    alternate_triggers: HashSet<char>,
 }
 
 impl Engine {
     pub fn new(json_data: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let root: TrieNode = serde_json::from_str(json_data)?;
         let root = Arc::new(root);
+        let backing = Backing::Tree(root);
+        let path = vec![Self::root_entry(&backing)];
+        Ok(Self {
+            backing,
+            path,
+            buffer: String::new(),
+            active: false,
+            selected_candidate: 0,
+            history: FrequencyHistory::new(),
+            history_path: None,
+            reverse_index: OnceCell::new(),
+            trigger: '\\',
+            alternate_triggers: HashSet::new(),
+        })
+    }
+
+    /// Builds an engine from an [`EngineConfig`]: deep-merges every table in
+    /// `config.table_sources` into one trie, and wires up `config.trigger` /
+    /// `config.alternate_triggers` in place of the hardcoded `'\\'`.
+    pub fn new_with_config(config: &EngineConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut merged: Option<TrieNode> = None;
+        for source in &config.table_sources {
+            let json = std::fs::read_to_string(source)?;
+            let node: TrieNode = serde_json::from_str(&json)?;
+            merged = Some(match merged {
+                Some(existing) => Self::merge_trie_nodes(existing, node),
+                None => node,
+            });
+        }
+        let root = merged.ok_or("EngineConfig.table_sources must not be empty")?;
+        let root = Arc::new(root);
+        let backing = Backing::Tree(root);
+        let path = vec![Self::root_entry(&backing)];
+        Ok(Self {
+            backing,
+            path,
+            buffer: String::new(),
+            active: false,
+            selected_candidate: 0,
+            history: FrequencyHistory::new(),
+            history_path: None,
+            reverse_index: OnceCell::new(),
+            trigger: config.trigger,
+            alternate_triggers: config.alternate_triggers.iter().copied().collect(),
+        })
+    }
+
+    /// Deep-merges `b` into `a`: children are unioned recursively, and `>>` candidate lists are
+    /// concatenated with de-duplication (keeping `a`'s ordering, then any new entries from `b`).
+    fn merge_trie_nodes(mut a: TrieNode, b: TrieNode) -> TrieNode {
+        match (&mut a.candidates, b.candidates) {
+            (Some(a_candidates), Some(b_candidates)) => {
+                for candidate in b_candidates {
+                    if !a_candidates.contains(&candidate) {
+                        a_candidates.push(candidate);
+                    }
+                }
+            }
+            (a_candidates @ None, Some(b_candidates)) => *a_candidates = Some(b_candidates),
+            _ => {}
+        }
+
+        for (key, b_child) in b.children {
+            match a.children.remove(&key) {
+                Some(a_child) => {
+                    a.children.insert(key, Self::merge_trie_nodes(a_child, b_child));
+                }
+                None => {
+                    a.children.insert(key, b_child);
+                }
+            }
+        }
+
+        a
+    }
+
+    fn is_trigger(&self, c: char) -> bool {
+        c == self.trigger || self.alternate_triggers.contains(&c)
+    }
+
+    /// Like [`Self::new`], but also loads a per-user selection-frequency sidecar from
+    /// `history_path` (if it exists) so [`Self::get_candidates`] can rank by what this user
+    /// actually picks. The sidecar is flushed back on [`Self::deactivate`] and
+    /// [`Self::save_history`].
+    pub fn new_with_history(
+        json_data: &str,
+        history_path: impl Into<PathBuf>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut engine = Self::new(json_data)?;
+        let history_path = history_path.into();
+        if let Ok(contents) = std::fs::read_to_string(&history_path)
+            && let Ok(history) = serde_json::from_str(&contents)
+        {
+            engine.history = history;
+        }
+        engine.history_path = Some(history_path);
+        Ok(engine)
+    }
+
+    /// Loads a table previously produced by [`crate::compiled::compile`] and `mmap`s it rather
+    /// than deserializing it, so no `TrieNode` tree is built in memory as the table grows into
+    /// the tens of thousands of entries. Opening still bounds-checks every record once.
+    pub fn new_from_compiled(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let trie = CompiledTrie::open(path)?;
+        let backing = Backing::Compiled(Arc::new(trie));
+        let path = vec![Self::root_entry(&backing)];
         Ok(Self {
-            path: vec![Arc::clone(&root)],
-            root,
+            backing,
+            path,
             buffer: String::new(),
             active: false,
             selected_candidate: 0,
+            history: FrequencyHistory::new(),
+            history_path: None,
+            reverse_index: OnceCell::new(),
+            trigger: '\\',
+            alternate_triggers: HashSet::new(),
         })
     }
 
-    fn current_node(&self) -> Option<&Arc<TrieNode>> {
+    /// Persists the learned selection frequencies to the sidecar passed to
+    /// [`Self::new_with_history`]. A no-op if the engine wasn't constructed with a history path.
+    pub fn save_history(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(history_path) = &self.history_path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string(&self.history)?;
+        std::fs::write(history_path, json)?;
+        Ok(())
+    }
+
+    /// Records that `candidate` was chosen for the current `self.buffer`, so future
+    /// [`Self::get_candidates`] calls for the same buffer rank it higher.
+    fn record_selection(&mut self, candidate: String) {
+        *self
+            .history
+            .entry(self.buffer.clone())
+            .or_default()
+            .entry(candidate)
+            .or_insert(0) += 1;
+    }
+
+    /// Every trigger-prefixed key sequence that reaches `symbol`, shortest first. The whole trie is
+    /// only walked once, on the first call to this method (see [`Self::reverse_index`]).
+    pub fn lookup_sequences(&self, symbol: &str) -> Vec<String> {
+        let index = self
+            .reverse_index
+            .get_or_init(|| self.build_reverse_index());
+        let mut sequences = index.get(symbol).cloned().unwrap_or_default();
+        sequences.sort_by_key(|sequence| sequence.len());
+        sequences
+    }
+
+    fn build_reverse_index(&self) -> HashMap<String, Vec<String>> {
+        match &self.backing {
+            Backing::Tree(root) => Self::build_reverse_index_tree(root, self.trigger),
+            Backing::Compiled(trie) => {
+                Self::build_reverse_index_compiled(trie, trie.root_index(), self.trigger)
+            }
+        }
+    }
+
+    fn build_reverse_index_tree(root: &TrieNode, trigger: char) -> HashMap<String, Vec<String>> {
+        let mut index = HashMap::new();
+        Self::walk_reverse_index_tree(root, String::new(), trigger, &mut index);
+        index
+    }
+
+    fn walk_reverse_index_tree(
+        node: &TrieNode,
+        prefix: String,
+        trigger: char,
+        index: &mut HashMap<String, Vec<String>>,
+    ) {
+        if let Some(candidates) = &node.candidates {
+            for candidate in candidates {
+                index
+                    .entry(candidate.clone())
+                    .or_default()
+                    .push(format!("{trigger}{prefix}"));
+            }
+        }
+        for (key, child) in &node.children {
+            Self::walk_reverse_index_tree(child, format!("{prefix}{key}"), trigger, index);
+        }
+    }
+
+    fn build_reverse_index_compiled(
+        trie: &CompiledTrie,
+        root_index: u32,
+        trigger: char,
+    ) -> HashMap<String, Vec<String>> {
+        let mut index = HashMap::new();
+        Self::walk_reverse_index_compiled(trie, root_index, String::new(), trigger, &mut index);
+        index
+    }
+
+    fn walk_reverse_index_compiled(
+        trie: &CompiledTrie,
+        node_index: u32,
+        prefix: String,
+        trigger: char,
+        index: &mut HashMap<String, Vec<String>>,
+    ) {
+        for candidate in trie.candidates(node_index) {
+            index
+                .entry(candidate)
+                .or_default()
+                .push(format!("{trigger}{prefix}"));
+        }
+        for (ch, child_index) in trie.edges(node_index) {
+            Self::walk_reverse_index_compiled(trie, child_index, format!("{prefix}{ch}"), trigger, index);
+        }
+    }
+
+    fn root_entry(backing: &Backing) -> PathEntry {
+        match backing {
+            Backing::Tree(root) => PathEntry::Tree(Arc::clone(root)),
+            Backing::Compiled(trie) => PathEntry::Compiled(trie.root_index()),
+        }
+    }
+
+    fn current_entry(&self) -> Option<&PathEntry> {
         self.path.last()
     }
 
+    /// The candidate strings attached to `entry`, if any (mirrors `TrieNode::candidates`, but
+    /// works for either backing).
+    fn entry_candidates(&self, entry: &PathEntry) -> Option<Vec<String>> {
+        match entry {
+            PathEntry::Tree(node) => node.candidates.clone(),
+            PathEntry::Compiled(index) => {
+                let Backing::Compiled(trie) = &self.backing else {
+                    unreachable!("PathEntry::Compiled only occurs with a Compiled backing")
+                };
+                let candidates = trie.candidates(*index);
+                (!candidates.is_empty()).then_some(candidates)
+            }
+        }
+    }
+
+    fn entry_has_children(&self, entry: &PathEntry) -> bool {
+        match entry {
+            PathEntry::Tree(node) => !node.children.is_empty(),
+            PathEntry::Compiled(index) => {
+                let Backing::Compiled(trie) = &self.backing else {
+                    unreachable!("PathEntry::Compiled only occurs with a Compiled backing")
+                };
+                trie.has_children(*index)
+            }
+        }
+    }
+
+    fn entry_child(&self, entry: &PathEntry, c: char) -> Option<PathEntry> {
+        match entry {
+            PathEntry::Tree(node) => node
+                .children
+                .get(&c.to_string())
+                .map(|child| PathEntry::Tree(Arc::new(child.clone()))),
+            PathEntry::Compiled(index) => {
+                let Backing::Compiled(trie) = &self.backing else {
+                    unreachable!("PathEntry::Compiled only occurs with a Compiled backing")
+                };
+                trie.find_child(*index, c).map(PathEntry::Compiled)
+            }
+        }
+    }
+
     pub fn select_candidate(&mut self, index: usize) {
-        if let Some(node) = self.current_node()
-            && let Some(candidates) = &node.candidates
+        if let Some(entry) = self.current_entry()
+            && let Some(candidates) = self.entry_candidates(entry)
             && index < candidates.len()
         {
             self.selected_candidate = index;
@@ -68,19 +394,21 @@ impl Engine {
 
     pub fn process_key(&mut self, c: char) -> Vec<EngineAction> {
         match (self.active, c) {
-            (false, '\\') => {
+            (false, c) if self.is_trigger(c) => {
                 self.activate();
                 vec![EngineAction::UpdateComposition(self.buffer.clone())]
             }
             (false, _) => vec![EngineAction::Reject],
-            // Receiving backslash in active mode
-            (true, '\\') => {
-                let text = if self.buffer == "\\" {
-                    "\\".to_string()
+            // Receiving a trigger key in active mode
+            (true, c) if self.is_trigger(c) => {
+                let text = if self.buffer == self.trigger.to_string() {
+                    self.trigger.to_string()
                 } else if let candidates = self.get_candidates()
                     && self.selected_candidate < candidates.len()
                 {
-                    candidates[self.selected_candidate].clone()
+                    let chosen = candidates[self.selected_candidate].clone();
+                    self.record_selection(chosen.clone());
+                    chosen
                 } else {
                     self.buffer.clone()
                 };
@@ -95,15 +423,15 @@ impl Engine {
                 self.active = false;
                 vec![EngineAction::Reject]
             }
-            (true, '\x08') | (true, '\x7f') if self.buffer == "\\" => {
+            (true, '\x08') | (true, '\x7f') if self.buffer == self.trigger.to_string() => {
                 self.deactivate();
                 vec![EngineAction::UpdateComposition(String::new())]
             }
             (true, '\x08') | (true, '\x7f') => {
                 self.pop();
 
-                if let Some(current) = self.current_node()
-                    && let Some(candidates) = &current.candidates
+                if let Some(current) = self.current_entry()
+                    && let Some(candidates) = self.entry_candidates(current)
                     && !candidates.is_empty()
                 {
                     vec![EngineAction::ShowCandidates(self.buffer.clone())]
@@ -112,18 +440,15 @@ impl Engine {
                 }
             }
             (true, c) => {
-                let next_node_arc = self
-                    .current_node()
-                    .and_then(|node| node.children.get(&c.to_string()))
-                    .map(|n| Arc::new(n.clone()));
-
-                if let Some(next_node_arc) = next_node_arc {
-                    if next_node_arc.children.is_empty() {
-                        let candidates = next_node_arc.candidates.as_ref();
-                        let text = if candidates.map_or(true, |v| v.is_empty()) {
+                let next_entry = self.current_entry().and_then(|entry| self.entry_child(entry, c));
+
+                if let Some(next_entry) = next_entry {
+                    if !self.entry_has_children(&next_entry) {
+                        let candidates = self.entry_candidates(&next_entry);
+                        let text = if candidates.as_ref().map_or(true, |v| v.is_empty()) {
                             // No children, no candidates: commit buffer + char
                             format!("{}{}", self.buffer, c)
-                        } else if let Some(candidates) = candidates
+                        } else if let Some(candidates) = &candidates
                             && candidates.len() == 1
                         {
                             // No children, one candidate: commit it
@@ -137,10 +462,10 @@ impl Engine {
                         }
                     }
 
-                    self.push(next_node_arc, c);
+                    self.push(next_entry, c);
 
-                    if let Some(current) = self.current_node()
-                        && let Some(candidates) = &current.candidates
+                    if let Some(current) = self.current_entry()
+                        && let Some(candidates) = self.entry_candidates(current)
                         && !candidates.is_empty()
                     {
                         return vec![EngineAction::ShowCandidates(self.buffer.clone())];
@@ -156,20 +481,21 @@ impl Engine {
 
     pub fn activate(&mut self) {
         self.active = true;
-        self.buffer = "\\".to_string();
-        self.path = vec![Arc::clone(&self.root)];
+        self.buffer = self.trigger.to_string();
+        self.path = vec![Self::root_entry(&self.backing)];
         self.selected_candidate = 0;
     }
 
     pub fn deactivate(&mut self) {
         self.active = false;
         self.buffer.clear();
-        self.path = vec![Arc::clone(&self.root)];
+        self.path = vec![Self::root_entry(&self.backing)];
         self.selected_candidate = 0;
+        let _ = self.save_history();
     }
 
-    fn push(&mut self, next_node: Arc<TrieNode>, c: char) {
-        self.path.push(next_node);
+    fn push(&mut self, next_entry: PathEntry, c: char) {
+        self.path.push(next_entry);
         self.buffer.push(c);
         self.selected_candidate = 0;
     }
@@ -181,9 +507,236 @@ impl Engine {
     }
 
     pub fn get_candidates(&self) -> Vec<String> {
-        self.current_node()
-            .and_then(|node| node.candidates.clone())
-            .unwrap_or_default()
+        let mut candidates = self
+            .current_entry()
+            .and_then(|entry| self.entry_candidates(entry))
+            .unwrap_or_default();
+
+        if let Some(counts) = self.history.get(&self.buffer) {
+            candidates.sort_by_key(|candidate| {
+                std::cmp::Reverse(counts.get(candidate).copied().unwrap_or(0))
+            });
+        }
+
+        candidates
+    }
+
+    /// Collects every candidate reachable from the current node, depth-first, paired with
+    /// the key suffix that reaches it (e.g. after `\al`, `\alpha` yields `suffix: "pha"`).
+    ///
+    /// Unlike [`Self::get_candidates`], which only looks at the exact current node, this
+    /// walks the whole subtree so a partial prefix like `\al` can still surface `\alpha`.
+    /// Stops once `max_results` candidates have been collected.
+    pub fn get_completions(&self, max_results: usize) -> Vec<Completion> {
+        let mut results = Vec::new();
+        if let Some(entry) = self.current_entry().cloned() {
+            match &entry {
+                PathEntry::Tree(node) => {
+                    Self::collect_completions_tree(node, String::new(), max_results, &mut results)
+                }
+                PathEntry::Compiled(index) => {
+                    let Backing::Compiled(trie) = &self.backing else {
+                        unreachable!("PathEntry::Compiled only occurs with a Compiled backing")
+                    };
+                    Self::collect_completions_compiled(
+                        trie,
+                        *index,
+                        String::new(),
+                        max_results,
+                        &mut results,
+                    );
+                }
+            }
+        }
+        results
+    }
+
+    fn collect_completions_tree(
+        node: &TrieNode,
+        suffix: String,
+        max_results: usize,
+        results: &mut Vec<Completion>,
+    ) {
+        if results.len() >= max_results {
+            return;
+        }
+
+        if let Some(candidates) = &node.candidates {
+            for candidate in candidates {
+                if results.len() >= max_results {
+                    return;
+                }
+                results.push(Completion {
+                    suffix: suffix.clone(),
+                    text: candidate.clone(),
+                });
+            }
+        }
+
+        let mut children: Vec<(&String, &TrieNode)> = node.children.iter().collect();
+        children.sort_by_key(|(key, _)| *key);
+        for (key, child) in children {
+            if results.len() >= max_results {
+                return;
+            }
+            Self::collect_completions_tree(child, format!("{suffix}{key}"), max_results, results);
+        }
+    }
+
+    fn collect_completions_compiled(
+        trie: &CompiledTrie,
+        index: u32,
+        suffix: String,
+        max_results: usize,
+        results: &mut Vec<Completion>,
+    ) {
+        if results.len() >= max_results {
+            return;
+        }
+
+        for candidate in trie.candidates(index) {
+            if results.len() >= max_results {
+                return;
+            }
+            results.push(Completion {
+                suffix: suffix.clone(),
+                text: candidate,
+            });
+        }
+
+        for (ch, child_index) in trie.edges(index) {
+            if results.len() >= max_results {
+                return;
+            }
+            Self::collect_completions_compiled(
+                trie,
+                child_index,
+                format!("{suffix}{ch}"),
+                max_results,
+                results,
+            );
+        }
+    }
+
+    /// Finds candidates within `max_distance` edits of the typed buffer, for recovering from
+    /// typos (e.g. `\lmabda` should still surface `\lambda`'s candidate).
+    ///
+    /// Unlike [`Self::get_completions`], this always starts at the trie root rather than the
+    /// current node, since a typo can occur anywhere in the path. Results are sorted by
+    /// ascending distance, then by the order the trie walk found them in, and deduplicated by
+    /// candidate text (keeping each text's best distance).
+    pub fn fuzzy_candidates(&self, max_distance: u32) -> Vec<FuzzyMatch> {
+        let query: Vec<char> = self
+            .buffer
+            .strip_prefix(self.trigger)
+            .unwrap_or(&self.buffer)
+            .chars()
+            .collect();
+        let root_row: Vec<u32> = (0..=query.len() as u32).collect();
+
+        let mut raw = Vec::new();
+        match &self.backing {
+            Backing::Tree(root) => {
+                Self::fuzzy_dfs_tree(root, &query, &root_row, max_distance, &mut raw);
+            }
+            Backing::Compiled(trie) => {
+                Self::fuzzy_dfs_compiled(trie, trie.root_index(), &query, &root_row, max_distance, &mut raw);
+            }
+        }
+
+        Self::dedup_and_sort_fuzzy(raw)
+    }
+
+    /// One DP row of a Levenshtein edit-distance table: `row[j]` is the distance between the
+    /// trie key path walked so far (ending in `ch`) and `query[..j]`.
+    fn next_fuzzy_row(prev_row: &[u32], ch: char, query: &[char]) -> Vec<u32> {
+        let mut row = vec![prev_row[0] + 1];
+        for (j, &q) in query.iter().enumerate() {
+            let cost = u32::from(q != ch);
+            row.push((prev_row[j + 1] + 1).min(row[j] + 1).min(prev_row[j] + cost));
+        }
+        row
+    }
+
+    fn fuzzy_dfs_tree(
+        node: &TrieNode,
+        query: &[char],
+        prev_row: &[u32],
+        max_distance: u32,
+        raw: &mut Vec<(String, u32)>,
+    ) {
+        let mut children: Vec<(&String, &TrieNode)> = node.children.iter().collect();
+        children.sort_by_key(|(key, _)| *key);
+        for (key, child) in children {
+            let Some(ch) = key.chars().next() else {
+                continue;
+            };
+            let row = Self::next_fuzzy_row(prev_row, ch, query);
+            if *row.iter().min().unwrap() > max_distance {
+                continue;
+            }
+
+            let distance = row[query.len()];
+            if distance <= max_distance
+                && let Some(candidates) = &child.candidates
+            {
+                for candidate in candidates {
+                    raw.push((candidate.clone(), distance));
+                }
+            }
+
+            Self::fuzzy_dfs_tree(child, query, &row, max_distance, raw);
+        }
+    }
+
+    fn fuzzy_dfs_compiled(
+        trie: &CompiledTrie,
+        index: u32,
+        query: &[char],
+        prev_row: &[u32],
+        max_distance: u32,
+        raw: &mut Vec<(String, u32)>,
+    ) {
+        for (ch, child_index) in trie.edges(index) {
+            let row = Self::next_fuzzy_row(prev_row, ch, query);
+            if *row.iter().min().unwrap() > max_distance {
+                continue;
+            }
+
+            let distance = row[query.len()];
+            if distance <= max_distance {
+                for candidate in trie.candidates(child_index) {
+                    raw.push((candidate, distance));
+                }
+            }
+
+            Self::fuzzy_dfs_compiled(trie, child_index, query, &row, max_distance, raw);
+        }
+    }
+
+    fn dedup_and_sort_fuzzy(raw: Vec<(String, u32)>) -> Vec<FuzzyMatch> {
+        let mut best: HashMap<String, (u32, usize)> = HashMap::new();
+        for (order, (text, distance)) in raw.into_iter().enumerate() {
+            best.entry(text)
+                .and_modify(|(best_distance, best_order)| {
+                    if distance < *best_distance {
+                        *best_distance = distance;
+                        *best_order = order;
+                    }
+                })
+                .or_insert((distance, order));
+        }
+
+        let mut entries: Vec<(String, u32, usize)> = best
+            .into_iter()
+            .map(|(text, (distance, order))| (text, distance, order))
+            .collect();
+        entries.sort_by(|a, b| a.1.cmp(&b.1).then(a.2.cmp(&b.2)));
+
+        entries
+            .into_iter()
+            .map(|(text, distance, _)| FuzzyMatch { text, distance })
+            .collect()
     }
 }
 
@@ -377,6 +930,317 @@ mod tests {
         assert_eq!(res, vec![EngineAction::UpdateComposition("\\".to_string())]);
     }
 
+    #[test]
+    fn test_completions_walk_subtree() {
+        let mut engine = Engine::new(TEST_JSON).unwrap();
+        engine.process_key('\\');
+        engine.process_key('a');
+
+        // "\a" itself has no candidates, but "\alpha" is reachable beneath it.
+        let completions = engine.get_completions(10);
+        assert_eq!(
+            completions,
+            vec![Completion {
+                suffix: "lpha".to_string(),
+                text: "α".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_completions_respect_max_results() {
+        let mut engine = Engine::new(TEST_JSON).unwrap();
+        engine.process_key('\\');
+        engine.process_key('l');
+
+        // "\l" has its own candidates plus "\lam" beneath it; cap at 1 result.
+        let completions = engine.get_completions(1);
+        assert_eq!(completions.len(), 1);
+    }
+
+    #[test]
+    fn test_completions_are_ordered_deterministically() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let bytes = crate::compiled::compile(TEST_JSON).unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+
+        let mut tree_engine = Engine::new(TEST_JSON).unwrap();
+        tree_engine.process_key('\\');
+        let tree_completions = tree_engine.get_completions(100);
+
+        // Calling twice on a fresh tree-backed engine must yield the same order every time...
+        let mut tree_engine_again = Engine::new(TEST_JSON).unwrap();
+        tree_engine_again.process_key('\\');
+        assert_eq!(tree_completions, tree_engine_again.get_completions(100));
+
+        // ...and must match the compiled backing's (char-sorted) order, not just be internally
+        // consistent with itself.
+        let mut compiled_engine = Engine::new_from_compiled(file.path()).unwrap();
+        compiled_engine.process_key('\\');
+        assert_eq!(tree_completions, compiled_engine.get_completions(100));
+    }
+
+    #[test]
+    fn test_compiled_engine_matches_tree_engine() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let bytes = crate::compiled::compile(TEST_JSON).unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+
+        let mut engine = Engine::new_from_compiled(file.path()).unwrap();
+        engine.process_key('\\');
+        engine.process_key('l');
+        engine.process_key('a');
+        let res = engine.process_key('m');
+
+        // \lam -> λ (Leaf auto-commit), same as the JSON-backed engine.
+        assert_eq!(res, vec![EngineAction::Commit("λ".to_string())]);
+        assert!(!engine.active);
+    }
+
+    #[test]
+    fn test_compiled_engine_completions() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let bytes = crate::compiled::compile(TEST_JSON).unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+
+        let mut engine = Engine::new_from_compiled(file.path()).unwrap();
+        engine.process_key('\\');
+        engine.process_key('a');
+
+        let completions = engine.get_completions(10);
+        assert_eq!(
+            completions,
+            vec![Completion {
+                suffix: "lpha".to_string(),
+                text: "α".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_candidates_dedups_to_best_distance() {
+        let mut engine = Engine::new(TEST_JSON).unwrap();
+        engine.process_key('\\');
+        engine.process_key('l');
+        // buffer = "\l"; query = "l"
+
+        let matches = engine.fuzzy_candidates(2);
+        let lambda = matches.iter().find(|m| m.text == "λ").unwrap();
+        assert_eq!(lambda.distance, 0);
+        // "λ" is reachable via both "\l" (distance 0) and "\lam" (distance 2); only the
+        // better match should survive deduplication.
+        assert_eq!(matches.iter().filter(|m| m.text == "λ").count(), 1);
+    }
+
+    #[test]
+    fn test_fuzzy_candidates_recovers_from_typo() {
+        let mut engine = Engine::new(TEST_JSON).unwrap();
+        engine.process_key('\\');
+        engine.buffer = "\\lm".to_string(); // as if a typo landed one edit away from "\lam"
+
+        let matches = engine.fuzzy_candidates(1);
+        assert!(matches.iter().any(|m| m.text == "λ" && m.distance <= 1));
+    }
+
+    #[test]
+    fn test_fuzzy_candidates_respects_max_distance() {
+        let mut engine = Engine::new(TEST_JSON).unwrap();
+        engine.process_key('\\');
+        engine.buffer = "\\zzzzzz".to_string();
+
+        assert!(engine.fuzzy_candidates(1).is_empty());
+    }
+
+    #[test]
+    fn test_fuzzy_candidates_are_ordered_deterministically() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let bytes = crate::compiled::compile(TEST_JSON).unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+
+        // "x" is one edit away from every single-char root key, so several equal-distance
+        // matches tie and their relative order depends entirely on trie walk order.
+        let mut tree_engine = Engine::new(TEST_JSON).unwrap();
+        tree_engine.process_key('\\');
+        tree_engine.buffer = "\\x".to_string();
+        let tree_matches = tree_engine.fuzzy_candidates(1);
+        assert!(tree_matches.len() > 1);
+
+        let mut tree_engine_again = Engine::new(TEST_JSON).unwrap();
+        tree_engine_again.process_key('\\');
+        tree_engine_again.buffer = "\\x".to_string();
+        assert_eq!(tree_matches, tree_engine_again.fuzzy_candidates(1));
+
+        let mut compiled_engine = Engine::new_from_compiled(file.path()).unwrap();
+        compiled_engine.process_key('\\');
+        compiled_engine.buffer = "\\x".to_string();
+        assert_eq!(tree_matches, compiled_engine.fuzzy_candidates(1));
+    }
+
+    #[test]
+    fn test_get_candidates_ranks_by_learned_frequency() {
+        let mut engine = Engine::new(TEST_JSON).unwrap();
+        engine.process_key('\\');
+        engine.process_key('l');
+        assert_eq!(engine.get_candidates(), vec!["λ", "←"]);
+
+        // Pick "←" three times in a row; it should bubble to the front.
+        for _ in 0..3 {
+            let candidates = engine.get_candidates();
+            let idx = candidates.iter().position(|c| c == "←").unwrap();
+            engine.select_candidate(idx);
+            engine.process_key('\\'); // commits the selection and restarts composition
+            engine.process_key('l');
+        }
+
+        assert_eq!(engine.get_candidates(), vec!["←", "λ"]);
+    }
+
+    #[test]
+    fn test_history_persists_across_new_with_history() {
+        use tempfile::NamedTempFile;
+
+        let history_file = NamedTempFile::new().unwrap();
+        let history_path = history_file.path().to_path_buf();
+
+        {
+            let mut engine = Engine::new_with_history(TEST_JSON, &history_path).unwrap();
+            engine.process_key('\\');
+            engine.process_key('l');
+            engine.select_candidate(1);
+            engine.process_key('\\'); // records "←" for buffer "\l"; this commit does not flush
+            engine.deactivate(); // flushes the history to `history_path`
+        }
+
+        let mut engine = Engine::new_with_history(TEST_JSON, &history_path).unwrap();
+        engine.process_key('\\');
+        engine.process_key('l');
+        assert_eq!(engine.get_candidates(), vec!["←", "λ"]);
+    }
+
+    #[test]
+    fn test_lookup_sequences_finds_all_paths_shortest_first() {
+        let engine = Engine::new(TEST_JSON).unwrap();
+
+        // "λ" is reachable both at "\l" and at the deeper "\lam".
+        let sequences = engine.lookup_sequences("λ");
+        assert_eq!(sequences, vec!["\\l", "\\lam"]);
+    }
+
+    #[test]
+    fn test_lookup_sequences_unknown_symbol() {
+        let engine = Engine::new(TEST_JSON).unwrap();
+        assert!(engine.lookup_sequences("not in the table").is_empty());
+    }
+
+    #[test]
+    fn test_lookup_sequences_works_for_compiled_engine() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let bytes = crate::compiled::compile(TEST_JSON).unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+
+        let engine = Engine::new_from_compiled(file.path()).unwrap();
+        assert_eq!(engine.lookup_sequences("λ"), vec!["\\l", "\\lam"]);
+    }
+
+    #[test]
+    fn test_custom_trigger_key() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut table_file = NamedTempFile::new().unwrap();
+        table_file.write_all(TEST_JSON.as_bytes()).unwrap();
+
+        let config = EngineConfig {
+            trigger: ';',
+            alternate_triggers: vec![],
+            table_sources: vec![table_file.path().to_string_lossy().into_owned()],
+        };
+        let mut engine = Engine::new_with_config(&config).unwrap();
+
+        assert_eq!(engine.process_key('\\'), vec![EngineAction::Reject]);
+
+        let res = engine.process_key(';');
+        assert_eq!(res, vec![EngineAction::UpdateComposition(";".to_string())]);
+        assert_eq!(engine.buffer, ";");
+
+        engine.process_key('l');
+        engine.process_key('a');
+        let res = engine.process_key('m');
+        assert_eq!(res, vec![EngineAction::Commit("λ".to_string())]);
+    }
+
+    #[test]
+    fn test_alternate_trigger_key() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut table_file = NamedTempFile::new().unwrap();
+        table_file.write_all(TEST_JSON.as_bytes()).unwrap();
+
+        let config = EngineConfig {
+            trigger: '\\',
+            alternate_triggers: vec![';'],
+            table_sources: vec![table_file.path().to_string_lossy().into_owned()],
+        };
+        let mut engine = Engine::new_with_config(&config).unwrap();
+
+        let res = engine.process_key(';');
+        // Activating via the alternate trigger still composes using the canonical one.
+        assert_eq!(res, vec![EngineAction::UpdateComposition("\\".to_string())]);
+        assert_eq!(engine.buffer, "\\");
+    }
+
+    #[test]
+    fn test_new_with_config_merges_tables() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut table_a = NamedTempFile::new().unwrap();
+        table_a
+            .write_all(r#"{"l": {">>": ["λ"]}}"#.as_bytes())
+            .unwrap();
+
+        let mut table_b = NamedTempFile::new().unwrap();
+        table_b
+            .write_all(r#"{"l": {">>": ["λ", "←"]}, "z": {">>": ["Z"]}}"#.as_bytes())
+            .unwrap();
+
+        let config = EngineConfig {
+            trigger: '\\',
+            alternate_triggers: vec![],
+            table_sources: vec![
+                table_a.path().to_string_lossy().into_owned(),
+                table_b.path().to_string_lossy().into_owned(),
+            ],
+        };
+        let mut engine = Engine::new_with_config(&config).unwrap();
+
+        engine.process_key('\\');
+        engine.process_key('l');
+        // "λ" from both tables is de-duplicated; "←" is merged in from table_b.
+        assert_eq!(engine.get_candidates(), vec!["λ", "←"]);
+
+        engine.deactivate();
+        engine.process_key('\\');
+        let res = engine.process_key('z');
+        assert_eq!(res, vec![EngineAction::Commit("Z".to_string())]);
+    }
+
     #[test]
     fn test_selection_commit() {
         let mut engine = Engine::new(TEST_JSON).unwrap();
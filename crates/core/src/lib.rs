@@ -0,0 +1,5 @@
+pub mod compiled;
+pub mod engine;
+
+pub use compiled::{compile, CompiledTrie};
+pub use engine::*;